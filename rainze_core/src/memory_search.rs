@@ -29,9 +29,44 @@
 //! index = VectorIndex.load("./data/faiss_index.bin")
 //! ```
 
-use faiss::{index_factory, Index, MetricType};
+use faiss::selector::IdSelector;
+use faiss::{index_factory, Idx, Index, MetricType};
 use pyo3::prelude::*;
-use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// 距离度量类型 / Distance metric type
+///
+/// 在构造索引时选择相似度度量方式。
+/// Selects the similarity measure when constructing an index.
+#[pyclass(eq, eq_int)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Metric {
+    /// 内积相似度 / Inner product similarity
+    InnerProduct,
+    /// 欧氏距离（L2）/ Euclidean (L2) distance
+    L2,
+}
+
+impl From<Metric> for MetricType {
+    fn from(metric: Metric) -> Self {
+        match metric {
+            Metric::InnerProduct => MetricType::InnerProduct,
+            Metric::L2 => MetricType::L2,
+        }
+    }
+}
+
+impl From<MetricType> for Metric {
+    fn from(metric: MetricType) -> Self {
+        match metric {
+            MetricType::L2 => Metric::L2,
+            // 其余度量在本封装中按内积处理 / Other metrics are treated as inner product here
+            _ => Metric::InnerProduct,
+        }
+    }
+}
 
 /// FAISS 向量索引封装 / FAISS Vector Index Wrapper
 ///
@@ -40,12 +75,57 @@ use std::sync::{Arc, Mutex};
 ///
 /// # Thread Safety / 线程安全
 ///
-/// 使用 Arc<Mutex<>> 包装以支持多线程访问。
-/// Wrapped with Arc<Mutex<>> for multi-threaded access.
+/// 使用 Arc<RwLock<>> 包装，读多写少场景下允许并发读取。
+/// Wrapped with Arc<RwLock<>>, allowing concurrent reads in read-heavy workloads.
 #[pyclass]
 pub struct VectorIndex {
-    index: Arc<Mutex<faiss::index::IndexImpl>>,
+    index: Arc<RwLock<faiss::index::IndexImpl>>,
     dimension: u32,
+    /// id → 文档元数据映射 / id → document metadata map
+    ///
+    /// 进程内 docstore，将 FAISS 自动分配的 id 关联到任意 Python 对象。
+    /// In-process docstore mapping auto-assigned FAISS ids to arbitrary Python objects.
+    docstore: Arc<Mutex<HashMap<i64, Py<PyAny>>>>,
+    /// 是否在入库与查询前 L2 归一化 / Whether to L2-normalize before add and query
+    ///
+    /// 开启后内积分数等价于余弦相似度。
+    /// When enabled, inner-product scores are equivalent to cosine similarity.
+    normalize: bool,
+    /// 构造时选择的距离度量 / Distance metric chosen at construction
+    metric: Metric,
+    /// 自增 id 分配器 / Monotonic id allocator
+    ///
+    /// 除 `reset` 清空索引时归零外只增不减，即使 `remove_ids` 使 `ntotal`
+    /// 收缩也不会回退；`add_vectors_with_ids` 会把它推进到所给最大 id 之后，
+    /// 避免自动分配的 id 与仍然存在的向量发生冲突。
+    /// Only `reset`, which empties the index, rewinds it; it does not rewind when
+    /// `remove_ids` shrinks `ntotal`, and `add_vectors_with_ids` advances it past
+    /// the largest supplied id, so auto-assigned ids never collide with vectors
+    /// that still exist.
+    next_id: Arc<AtomicI64>,
+}
+
+/// 计算索引文件旁边的元数据文件路径 / Sidecar metadata path next to the index file
+fn metadata_path(path: &str) -> String {
+    format!("{}.meta.json", path)
+}
+
+/// 对向量做 L2 归一化 / L2-normalize a vector in place
+///
+/// 零范数向量保持不变以避免除零。
+/// Zero-norm vectors are left untouched to avoid division by zero.
+fn l2_normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for x in vector.iter_mut() {
+            *x /= norm;
+        }
+    }
+}
+
+/// 两个向量的内积 / Inner product of two vectors
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
 }
 
 #[pymethods]
@@ -55,14 +135,17 @@ impl VectorIndex {
     /// # Arguments / 参数
     ///
     /// - `dimension`: 向量维度 / Vector dimension (e.g., 768)
+    /// - `normalize`: 是否按余弦相似度归一化 / Normalize for cosine similarity
     ///
     /// # Returns / 返回
     ///
     /// 新的 VectorIndex 实例 / New VectorIndex instance
     #[staticmethod]
-    fn new(dimension: u32) -> PyResult<Self> {
-        // 使用 Flat 索引和内积相似度 / Use Flat index with inner product
-        let index = index_factory(dimension, "Flat", MetricType::InnerProduct)
+    #[pyo3(signature = (dimension, normalize = false))]
+    fn new(dimension: u32, normalize: bool) -> PyResult<Self> {
+        // 使用 Flat 索引和内积相似度，并包裹 IDMap 以支持自定义 id
+        // Use Flat index with inner product, wrapped in IDMap for custom ids
+        let index = index_factory(dimension, "IDMap2,Flat", MetricType::InnerProduct)
             .map_err(|e| {
                 pyo3::exceptions::PyRuntimeError::new_err(format!(
                     "Failed to create FAISS index: {}",
@@ -71,11 +154,124 @@ impl VectorIndex {
             })?;
 
         Ok(VectorIndex {
-            index: Arc::new(Mutex::new(index)),
+            index: Arc::new(RwLock::new(index)),
+            dimension,
+            docstore: Arc::new(Mutex::new(HashMap::new())),
+            normalize,
+            metric: Metric::InnerProduct,
+            next_id: Arc::new(AtomicI64::new(0)),
+        })
+    }
+
+    /// 使用 FAISS 工厂描述字符串创建索引 / Create index from a FAISS factory description
+    ///
+    /// 允许使用任意 FAISS 工厂字符串构建压缩或倒排索引，以便扩展到
+    /// 大规模向量集合。例如 `"IVF4096,PQ16"`、`"HNSW32"`、`"PCA32,IVF1,PQ8"`。
+    /// Builds a compressed or inverted index from an arbitrary FAISS factory
+    /// string, enabling scaling beyond brute-force `Flat`. For example
+    /// `"IVF4096,PQ16"`, `"HNSW32"`, or `"PCA32,IVF1,PQ8"`.
+    ///
+    /// # Arguments / 参数
+    ///
+    /// - `dimension`: 向量维度 / Vector dimension (e.g., 768)
+    /// - `description`: FAISS 工厂描述字符串 / FAISS factory description string
+    /// - `metric`: 距离度量 / Distance metric
+    /// - `normalize`: 是否按余弦相似度归一化 / Normalize for cosine similarity
+    ///
+    /// # Note / 注意
+    ///
+    /// 压缩/倒排索引在调用 `add_vectors` 之前必须先用代表性向量训练，
+    /// 参见 [`train`](#method.train)。
+    /// Compressed/inverted indices must be trained with representative
+    /// vectors via [`train`](#method.train) before `add_vectors` will accept data.
+    #[staticmethod]
+    #[pyo3(signature = (dimension, description, metric, normalize = false))]
+    fn new_with_description(
+        dimension: u32,
+        description: &str,
+        metric: Metric,
+        normalize: bool,
+    ) -> PyResult<Self> {
+        // 确保包裹 IDMap，以支持自定义 id 与按 id 删除
+        // Ensure an IDMap wrapper so custom ids and delete-by-id are supported
+        let description = if description.contains("IDMap") {
+            description.to_string()
+        } else {
+            format!("IDMap2,{}", description)
+        };
+        let index = index_factory(dimension, &description, metric).map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!(
+                "Failed to create FAISS index: {}",
+                e
+            ))
+        })?;
+
+        Ok(VectorIndex {
+            index: Arc::new(RwLock::new(index)),
             dimension,
+            docstore: Arc::new(Mutex::new(HashMap::new())),
+            normalize,
+            metric,
+            next_id: Arc::new(AtomicI64::new(0)),
         })
     }
 
+    /// 索引是否已训练 / Whether the index has been trained
+    ///
+    /// `Flat` 等索引天然已训练；IVF/PQ 等索引需要显式训练。
+    /// Indices such as `Flat` are trained by construction; IVF/PQ indices
+    /// require an explicit training pass.
+    fn is_trained(&self) -> PyResult<bool> {
+        let index = self.index.read().map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Lock error: {}", e))
+        })?;
+
+        Ok(index.is_trained())
+    }
+
+    /// 使用代表性向量训练索引 / Train the index on representative vectors
+    ///
+    /// 压缩/倒排索引在添加数据前需要一次训练过程来学习量化器或聚类中心。
+    /// Compressed/inverted indices need a training pass to learn quantizers or
+    /// cluster centroids before data can be added.
+    ///
+    /// # Arguments / 参数
+    ///
+    /// - `vectors`: 训练向量列表 / List of training vectors
+    fn train(&self, vectors: Vec<Vec<f32>>) -> PyResult<()> {
+        for (i, vec) in vectors.iter().enumerate() {
+            if vec.len() != self.dimension as usize {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "Vector {} has dimension {}, expected {}",
+                    i,
+                    vec.len(),
+                    self.dimension
+                )));
+            }
+        }
+
+        let mut flat_vectors: Vec<f32> =
+            vectors.iter().flat_map(|v| v.iter().copied()).collect();
+
+        // 余弦模式下归一化训练向量，使其与入库/查询处于同一空间
+        // Normalize training vectors in cosine mode so they share the space of add/search
+        if self.normalize {
+            for chunk in flat_vectors.chunks_mut(self.dimension as usize) {
+                l2_normalize(chunk);
+            }
+        }
+
+        let mut index = self.index.write().map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Lock error: {}", e))
+        })?;
+
+        index.train(&flat_vectors).map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to train index: {}", e))
+        })?;
+
+        Ok(())
+    }
+
     /// 添加向量到索引 / Add vectors to index
     ///
     /// # Arguments / 参数
@@ -99,26 +295,189 @@ impl VectorIndex {
         }
 
         // 展平向量 / Flatten vectors
-        let flat_vectors: Vec<f32> =
+        let mut flat_vectors: Vec<f32> =
             vectors.iter().flat_map(|v| v.iter().copied()).collect();
 
+        // 余弦模式下归一化 / Normalize in cosine mode
+        if self.normalize {
+            for chunk in flat_vectors.chunks_mut(self.dimension as usize) {
+                l2_normalize(chunk);
+            }
+        }
+
         // 获取锁并添加 / Lock and add
-        let mut index = self.index.lock().map_err(|e| {
+        let mut index = self.index.write().map_err(|e| {
             pyo3::exceptions::PyRuntimeError::new_err(format!("Lock error: {}", e))
         })?;
 
-        let start_id = index.ntotal() as i64;
+        // 压缩/倒排索引必须先训练 / Compressed/inverted indices must be trained first
+        if !index.is_trained() {
+            return Err(pyo3::exceptions::PyRuntimeError::new_err(
+                "Index is not trained; call train() with representative vectors before add_vectors",
+            ));
+        }
+
+        // 从自增计数器分配 id 并通过 add_with_ids 添加
+        // Allocate ids from the monotonic counter and add via add_with_ids
+        //
+        // 底层索引包裹了 IDMap，裸 `add` 会报错，必须显式提供 id；计数器不随删除回退，
+        // 因此删除后再添加不会重用仍然存在的 id。
+        // The underlying index is wrapped in an IDMap, where a bare `add` errors, so
+        // ids must be supplied explicitly; the counter does not rewind on delete, so
+        // adding after a delete does not reuse ids that still exist.
+        let start_id = self.next_id.fetch_add(vectors.len() as i64, Ordering::SeqCst);
+        let end_id = start_id + vectors.len() as i64;
+        let idx: Vec<Idx> = (start_id..end_id).map(Idx::new).collect();
 
-        index.add(&flat_vectors).map_err(|e| {
+        index.add_with_ids(&flat_vectors, &idx).map_err(|e| {
             pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to add vectors: {}", e))
         })?;
 
-        let end_id = index.ntotal() as i64;
-
         // 返回 ID 列表 / Return ID list
         Ok((start_id..end_id).collect())
     }
 
+    /// 添加向量并关联元数据 / Add vectors together with metadata
+    ///
+    /// 将每个向量与一个任意 Python 对象关联，存入进程内 docstore，
+    /// 键为 FAISS 自动分配的 id。这样调用方无需自行维护位置到文档的映射。
+    /// Associates each vector with an arbitrary Python object in the in-process
+    /// docstore, keyed by the auto-assigned FAISS id, so callers need not keep an
+    /// external position→document mapping.
+    ///
+    /// # Arguments / 参数
+    ///
+    /// - `vectors`: 向量列表 / List of vectors
+    /// - `metadatas`: 与向量一一对应的元数据对象 / Metadata objects, one per vector
+    ///
+    /// # Returns / 返回
+    ///
+    /// 添加的向量 ID 列表 / List of added vector IDs
+    fn add_vectors_with_metadata(
+        &self,
+        vectors: Vec<Vec<f32>>,
+        metadatas: Vec<Py<PyAny>>,
+    ) -> PyResult<Vec<i64>> {
+        if vectors.len() != metadatas.len() {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "Got {} vectors but {} metadata entries",
+                vectors.len(),
+                metadatas.len()
+            )));
+        }
+
+        let ids = self.add_vectors(vectors)?;
+
+        let mut docstore = self.docstore.lock().map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Lock error: {}", e))
+        })?;
+        for (&id, metadata) in ids.iter().zip(metadatas.into_iter()) {
+            docstore.insert(id, metadata);
+        }
+
+        Ok(ids)
+    }
+
+    /// 使用调用方提供的 id 添加向量 / Add vectors with caller-supplied ids
+    ///
+    /// 借助底层的 IDMap，id 在重建后保持稳定，不会像顺序分配那样在删除
+    /// 早期向量后发生偏移。
+    /// Backed by the underlying IDMap, ids stay stable across rebuilds instead
+    /// of shifting when earlier vectors are removed, unlike sequential assignment.
+    ///
+    /// # Arguments / 参数
+    ///
+    /// - `vectors`: 向量列表 / List of vectors
+    /// - `ids`: 与向量一一对应的 id / Ids, one per vector
+    fn add_vectors_with_ids(&self, vectors: Vec<Vec<f32>>, ids: Vec<i64>) -> PyResult<()> {
+        if vectors.len() != ids.len() {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "Got {} vectors but {} ids",
+                vectors.len(),
+                ids.len()
+            )));
+        }
+
+        for (i, vec) in vectors.iter().enumerate() {
+            if vec.len() != self.dimension as usize {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "Vector {} has dimension {}, expected {}",
+                    i,
+                    vec.len(),
+                    self.dimension
+                )));
+            }
+        }
+
+        let mut flat_vectors: Vec<f32> =
+            vectors.iter().flat_map(|v| v.iter().copied()).collect();
+        if self.normalize {
+            for chunk in flat_vectors.chunks_mut(self.dimension as usize) {
+                l2_normalize(chunk);
+            }
+        }
+        let idx: Vec<Idx> = ids.iter().map(|&id| Idx::new(id)).collect();
+
+        let mut index = self.index.write().map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Lock error: {}", e))
+        })?;
+
+        if !index.is_trained() {
+            return Err(pyo3::exceptions::PyRuntimeError::new_err(
+                "Index is not trained; call train() with representative vectors before add_vectors_with_ids",
+            ));
+        }
+
+        index.add_with_ids(&flat_vectors, &idx).map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to add vectors: {}", e))
+        })?;
+
+        // 推进自增计数器，使之后自动分配的 id 不与这些 id 冲突
+        // Advance the counter so later auto-assigned ids do not collide with these
+        if let Some(&max_id) = ids.iter().max() {
+            self.next_id.fetch_max(max_id.saturating_add(1), Ordering::SeqCst);
+        }
+
+        Ok(())
+    }
+
+    /// 按 id 删除向量 / Remove vectors by id
+    ///
+    /// 支持源文档更新或撤回时的删除工作流，无需全量重置。
+    /// Supports the delete-by-id workflow when a source document is updated or
+    /// retracted, without an all-or-nothing reset.
+    ///
+    /// # Returns / 返回
+    ///
+    /// 实际删除的向量数量 / Number of vectors actually removed
+    fn remove_ids(&self, ids: Vec<i64>) -> PyResult<usize> {
+        let idx: Vec<Idx> = ids.iter().map(|&id| Idx::new(id)).collect();
+        let selector = IdSelector::batch(&idx).map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!(
+                "Failed to build id selector: {}",
+                e
+            ))
+        })?;
+
+        let mut index = self.index.write().map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Lock error: {}", e))
+        })?;
+
+        let removed = index.remove_ids(&selector).map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to remove ids: {}", e))
+        })?;
+
+        // 同步清理 docstore / Keep the docstore in sync
+        let mut docstore = self.docstore.lock().map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Lock error: {}", e))
+        })?;
+        for id in ids {
+            docstore.remove(&id);
+        }
+
+        Ok(removed)
+    }
+
     /// 搜索最相似的向量 / Search for most similar vectors
     ///
     /// # Arguments / 参数
@@ -139,8 +498,14 @@ impl VectorIndex {
             )));
         }
 
+        // 余弦模式下归一化查询 / Normalize the query in cosine mode
+        let mut query = query;
+        if self.normalize {
+            l2_normalize(&mut query);
+        }
+
         // 获取锁并搜索 / Lock and search
-        let index = self.index.lock().map_err(|e| {
+        let index = self.index.read().map_err(|e| {
             pyo3::exceptions::PyRuntimeError::new_err(format!("Lock error: {}", e))
         })?;
 
@@ -159,9 +524,210 @@ impl VectorIndex {
         Ok(results)
     }
 
+    /// 搜索并返回关联的元数据 / Search and return associated metadata
+    ///
+    /// 与 [`search`](#method.search) 相同，但额外从 docstore 取出每个命中的元数据。
+    /// 未关联元数据的 id 返回 `None`。
+    /// Same as [`search`](#method.search) but additionally retrieves each hit's
+    /// metadata from the docstore. Ids without stored metadata yield `None`.
+    ///
+    /// # Returns / 返回
+    ///
+    /// 元组列表 (ID, 相似度分数, 元数据) / List of tuples (ID, score, metadata)
+    fn search_with_metadata(
+        &self,
+        py: Python<'_>,
+        query: Vec<f32>,
+        k: usize,
+    ) -> PyResult<Vec<(i64, f32, Py<PyAny>)>> {
+        let hits = self.search(query, k)?;
+
+        let docstore = self.docstore.lock().map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Lock error: {}", e))
+        })?;
+
+        let results = hits
+            .into_iter()
+            .map(|(id, score)| {
+                let id = id.to_native();
+                let metadata = match docstore.get(&id) {
+                    Some(obj) => obj.clone_ref(py),
+                    None => py.None(),
+                };
+                (id, score, metadata)
+            })
+            .collect();
+
+        Ok(results)
+    }
+
+    /// 带谓词过滤的搜索 / Search with predicate-based filtering
+    ///
+    /// 在不重建子索引的前提下限制可返回的向量集合。通过超取候选、丢弃被
+    /// 过滤掉的 id，再截断到 `k`，使结果在候选足够时始终包含 `k` 个允许的命中。
+    /// `allowed_ids` 为白名单，`banned_ids` 为黑名单，可单独或组合使用。
+    /// Restricts which vectors may be returned without rebuilding a sub-index.
+    /// Over-fetches candidates, drops ids excluded by the filter, then truncates
+    /// to `k`, so the result always contains `k` allowed hits when enough exist.
+    /// `allowed_ids` is a whitelist and `banned_ids` a blacklist; either or both
+    /// may be supplied.
+    ///
+    /// # Returns / 返回
+    ///
+    /// 元组列表 (ID, 相似度分数) / List of tuples (ID, score)
+    #[pyo3(signature = (query, k, allowed_ids = None, banned_ids = None))]
+    fn search_filtered(
+        &self,
+        query: Vec<f32>,
+        k: usize,
+        allowed_ids: Option<Vec<i64>>,
+        banned_ids: Option<Vec<i64>>,
+    ) -> PyResult<Vec<(i64, f32)>> {
+        let allowed: Option<HashSet<i64>> = allowed_ids.map(|ids| ids.into_iter().collect());
+        let banned: HashSet<i64> = banned_ids.into_iter().flatten().collect();
+
+        let total = {
+            let index = self.index.read().map_err(|e| {
+                pyo3::exceptions::PyRuntimeError::new_err(format!("Lock error: {}", e))
+            })?;
+            index.ntotal() as usize
+        };
+
+        // 逐步扩大取回范围，直到凑够 k 个允许命中或耗尽索引
+        // Progressively widen the fetch until k allowed hits are found or the index is exhausted
+        let mut fetch_k = (k * 4).max(k + 10);
+        loop {
+            let capped = fetch_k.min(total);
+            let candidates = self.search(query.clone(), capped)?;
+
+            let filtered: Vec<(i64, f32)> = candidates
+                .into_iter()
+                .filter_map(|(idx, score)| {
+                    let id = idx.to_native();
+                    if id < 0 {
+                        return None;
+                    }
+                    if banned.contains(&id) {
+                        return None;
+                    }
+                    if let Some(allowed) = &allowed {
+                        if !allowed.contains(&id) {
+                            return None;
+                        }
+                    }
+                    Some((id, score))
+                })
+                .take(k)
+                .collect();
+
+            if filtered.len() >= k || capped >= total {
+                return Ok(filtered);
+            }
+            fetch_k *= 2;
+        }
+    }
+
+    /// 最大边际相关性（MMR）重排搜索 / Maximal Marginal Relevance (MMR) search
+    ///
+    /// 先取回 `fetch_k` 个候选（`fetch_k > k`），重建它们的嵌入向量，再贪心地
+    /// 选出 `k` 个结果：每一步挑选使
+    /// `lambda_mult * sim(query, d) - (1 - lambda_mult) * max_s sim(d, s)`
+    /// 最大的候选 `d`（`s` 为已选集合，`sim` 为索引度量，归一化时即余弦）。
+    /// Fetches `fetch_k` candidates (`fetch_k > k`), reconstructs their embeddings,
+    /// then greedily selects `k` results, at each step picking the candidate `d`
+    /// maximizing `lambda_mult * sim(query, d) - (1 - lambda_mult) * max_s sim(d, s)`
+    /// over the already-selected set `s` (`sim` is the index metric, cosine when
+    /// normalized). Trades a little relevance for diversity.
+    ///
+    /// # Returns / 返回
+    ///
+    /// 元组列表 (ID, 原始查询相似度分数) / List of tuples (ID, original query-similarity score)
+    ///
+    /// # Note / 注意
+    ///
+    /// 仅支持内积/余弦索引；冗余项以内积衡量相似度，对 L2 索引没有意义，
+    /// 故 `Metric::L2` 构造的索引会返回错误。
+    /// Only inner-product/cosine indices are supported; the redundancy term
+    /// measures similarity via inner product, which is meaningless for L2, so an
+    /// index built with `Metric::L2` returns an error.
+    #[pyo3(signature = (query, k, fetch_k, lambda_mult = 0.5))]
+    fn max_marginal_relevance_search(
+        &self,
+        query: Vec<f32>,
+        k: usize,
+        fetch_k: usize,
+        lambda_mult: f32,
+    ) -> PyResult<Vec<(i64, f32)>> {
+        if matches!(self.metric, Metric::L2) {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "max_marginal_relevance_search requires an inner-product or cosine index, not L2",
+            ));
+        }
+
+        // 取回候选及其查询相似度 / Fetch candidates with their query similarity
+        let candidates = self.search(query, fetch_k)?;
+
+        // 重建候选嵌入 / Reconstruct candidate embeddings
+        let mut index = self.index.write().map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Lock error: {}", e))
+        })?;
+        let mut pool: Vec<(i64, f32, Vec<f32>)> = Vec::with_capacity(candidates.len());
+        for (idx, score) in candidates {
+            let id = idx.to_native();
+            // FAISS 在结果不足时用 -1 填充 / FAISS pads missing results with -1
+            if id < 0 {
+                continue;
+            }
+            let embedding = index.reconstruct(idx).map_err(|e| {
+                pyo3::exceptions::PyRuntimeError::new_err(format!(
+                    "Failed to reconstruct vector {}: {}",
+                    id, e
+                ))
+            })?;
+            pool.push((id, score, embedding));
+        }
+        drop(index);
+
+        // 贪心选择 / Greedy selection
+        let mut selected: Vec<usize> = Vec::new();
+        let mut remaining: Vec<usize> = (0..pool.len()).collect();
+        let mut results: Vec<(i64, f32)> = Vec::new();
+
+        while results.len() < k && !remaining.is_empty() {
+            let mut best_pos = 0;
+            let mut best_mmr = f32::NEG_INFINITY;
+            for (pos, &ci) in remaining.iter().enumerate() {
+                let query_sim = pool[ci].1;
+                let redundancy = if selected.is_empty() {
+                    0.0
+                } else {
+                    selected
+                        .iter()
+                        .map(|&si| dot(&pool[ci].2, &pool[si].2))
+                        .fold(f32::NEG_INFINITY, f32::max)
+                };
+                let mmr = lambda_mult * query_sim - (1.0 - lambda_mult) * redundancy;
+                if mmr > best_mmr {
+                    best_mmr = mmr;
+                    best_pos = pos;
+                }
+            }
+            let chosen = remaining.remove(best_pos);
+            selected.push(chosen);
+            results.push((pool[chosen].0, pool[chosen].1));
+        }
+
+        Ok(results)
+    }
+
     /// 保存索引到文件 / Save index to file
-    fn save(&self, path: &str) -> PyResult<()> {
-        let index = self.index.lock().map_err(|e| {
+    ///
+    /// 除 FAISS 索引本身外，同时把 id→元数据映射写入同名的 `.meta.json`
+    /// 旁车文件，使索引与其负载一起迁移。
+    /// Besides the FAISS index itself, writes the id→metadata map to a sibling
+    /// `.meta.json` file so the index and its payloads travel together.
+    fn save(&self, py: Python<'_>, path: &str) -> PyResult<()> {
+        let index = self.index.read().map_err(|e| {
             pyo3::exceptions::PyRuntimeError::new_err(format!("Lock error: {}", e))
         })?;
 
@@ -169,27 +735,88 @@ impl VectorIndex {
             pyo3::exceptions::PyIOError::new_err(format!("Failed to save index: {}", e))
         })?;
 
+        // 将 docstore 序列化为 JSON 旁车文件 / Serialize the docstore to a JSON sidecar
+        let docstore = self.docstore.lock().map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Lock error: {}", e))
+        })?;
+        let mapping = pyo3::types::PyDict::new(py);
+        for (id, obj) in docstore.iter() {
+            mapping.set_item(id.to_string(), obj.bind(py))?;
+        }
+        let sidecar = pyo3::types::PyDict::new(py);
+        sidecar.set_item("normalize", self.normalize)?;
+        sidecar.set_item("next_id", self.next_id.load(Ordering::SeqCst))?;
+        sidecar.set_item("docstore", mapping)?;
+        let json = py.import("json")?;
+        let serialized: String = json
+            .call_method1("dumps", (sidecar,))?
+            .extract()?;
+        std::fs::write(metadata_path(path), serialized).map_err(|e| {
+            pyo3::exceptions::PyIOError::new_err(format!("Failed to save metadata: {}", e))
+        })?;
+
         Ok(())
     }
 
     /// 从文件加载索引 / Load index from file
+    ///
+    /// 若存在同名的 `.meta.json` 旁车文件，则一并恢复 id→元数据映射。
+    /// If a sibling `.meta.json` file exists, the id→metadata map is restored too.
     #[staticmethod]
-    fn load(path: &str) -> PyResult<Self> {
+    fn load(py: Python<'_>, path: &str) -> PyResult<Self> {
         let index = faiss::read_index(path).map_err(|e| {
             pyo3::exceptions::PyIOError::new_err(format!("Failed to load index: {}", e))
         })?;
 
         let dimension = index.d();
+        let metric = Metric::from(index.metric_type());
+
+        // 恢复元数据旁车文件（若存在）/ Restore the metadata sidecar if present
+        let mut docstore = HashMap::new();
+        let mut normalize = false;
+        // 无旁车文件时退回到 ntotal / Fall back to ntotal when no sidecar is present
+        let mut next_id = index.ntotal() as i64;
+        let meta_path = metadata_path(path);
+        if std::path::Path::new(&meta_path).exists() {
+            let serialized = std::fs::read_to_string(&meta_path).map_err(|e| {
+                pyo3::exceptions::PyIOError::new_err(format!("Failed to load metadata: {}", e))
+            })?;
+            let json = py.import("json")?;
+            let sidecar = json.call_method1("loads", (serialized,))?;
+            let sidecar = sidecar.downcast::<pyo3::types::PyDict>()?;
+            if let Some(flag) = sidecar.get_item("normalize")? {
+                normalize = flag.extract()?;
+            }
+            if let Some(value) = sidecar.get_item("next_id")? {
+                next_id = value.extract()?;
+            }
+            if let Some(mapping) = sidecar.get_item("docstore")? {
+                let mapping = mapping.downcast::<pyo3::types::PyDict>()?;
+                for (key, value) in mapping.iter() {
+                    let id: i64 = key.extract::<String>()?.parse().map_err(|e| {
+                        pyo3::exceptions::PyValueError::new_err(format!(
+                            "Invalid metadata id: {}",
+                            e
+                        ))
+                    })?;
+                    docstore.insert(id, value.unbind());
+                }
+            }
+        }
 
         Ok(VectorIndex {
-            index: Arc::new(Mutex::new(index)),
+            index: Arc::new(RwLock::new(index)),
             dimension,
+            docstore: Arc::new(Mutex::new(docstore)),
+            normalize,
+            metric,
+            next_id: Arc::new(AtomicI64::new(next_id)),
         })
     }
 
     /// 获取索引中的向量数量 / Get number of vectors
     fn ntotal(&self) -> PyResult<i64> {
-        let index = self.index.lock().map_err(|e| {
+        let index = self.index.read().map_err(|e| {
             pyo3::exceptions::PyRuntimeError::new_err(format!("Lock error: {}", e))
         })?;
 
@@ -204,8 +831,13 @@ impl VectorIndex {
     }
 
     /// 重置索引（清空所有向量）/ Reset index
+    ///
+    /// 同时清空 docstore 并把 id 计数器归零，使重置后重新分配的 id 不会
+    /// 命中遗留的元数据。
+    /// Also clears the docstore and rewinds the id counter so ids reassigned
+    /// after the reset do not surface leftover metadata.
     fn reset(&self) -> PyResult<()> {
-        let mut index = self.index.lock().map_err(|e| {
+        let mut index = self.index.write().map_err(|e| {
             pyo3::exceptions::PyRuntimeError::new_err(format!("Lock error: {}", e))
         })?;
 
@@ -213,6 +845,13 @@ impl VectorIndex {
             pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to reset index: {}", e))
         })?;
 
+        // 清空 docstore 并重置 id 计数器 / Clear the docstore and reset the id counter
+        let mut docstore = self.docstore.lock().map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Lock error: {}", e))
+        })?;
+        docstore.clear();
+        self.next_id.store(0, Ordering::SeqCst);
+
         Ok(())
     }
 }
@@ -221,6 +860,51 @@ impl VectorIndex {
 pub fn register_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
     let memory_module = PyModule::new(m.py(), "memory")?;
     memory_module.add_class::<VectorIndex>()?;
+    memory_module.add_class::<Metric>()?;
     m.add_submodule(&memory_module)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 默认构造的索引应能正常添加并检索 / A default-constructed index must add and search
+    #[test]
+    fn default_index_can_add_and_search() {
+        let index = VectorIndex::new(4, false).unwrap();
+
+        let ids = index
+            .add_vectors(vec![
+                vec![1.0, 0.0, 0.0, 0.0],
+                vec![0.0, 1.0, 0.0, 0.0],
+            ])
+            .unwrap();
+        assert_eq!(ids, vec![0, 1]);
+        assert_eq!(index.ntotal().unwrap(), 2);
+
+        let results = index.search(vec![1.0, 0.0, 0.0, 0.0], 1).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.to_native(), 0);
+    }
+
+    /// 删除后再添加不应重用已有 id / Adding after a delete must not reuse existing ids
+    #[test]
+    fn ids_are_not_reused_after_delete() {
+        let index = VectorIndex::new(4, false).unwrap();
+
+        index
+            .add_vectors(vec![
+                vec![1.0, 0.0, 0.0, 0.0],
+                vec![0.0, 1.0, 0.0, 0.0],
+                vec![0.0, 0.0, 1.0, 0.0],
+            ])
+            .unwrap();
+        index.remove_ids(vec![0]).unwrap();
+
+        // ntotal 收缩到 2，但新 id 必须从 3 开始而非重用 2
+        // ntotal shrinks to 2, but the new id must start at 3 rather than reuse 2
+        let ids = index.add_vectors(vec![vec![0.0, 0.0, 0.0, 1.0]]).unwrap();
+        assert_eq!(ids, vec![3]);
+    }
+}